@@ -3,8 +3,14 @@ use console::{pad_str, style, Alignment};
 use indenter::indented;
 use indoc::formatdoc;
 use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::fmt::{self, Display};
 use std::fmt::Write;
+use std::fs::OpenOptions;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::time::Instant;
 
 use crate::cli::command::Command;
 use crate::cli::version::VERSION;
@@ -20,139 +26,490 @@ use crate::toolset::ToolsetBuilder;
 /// Check rtx installation for possible problems.
 #[derive(Debug, clap::Args)]
 #[clap(verbatim_doc_comment, after_long_help = AFTER_LONG_HELP.as_str())]
-pub struct Doctor {}
+pub struct Doctor {
+    /// Output in JSON format
+    #[clap(long)]
+    json: bool,
+
+    /// Attempt to automatically fix the detected problems
+    #[clap(long)]
+    fix: bool,
+
+    /// Print a breakdown of time spent building the toolset and probing each plugin
+    /// (config file loading is not timed)
+    #[clap(long)]
+    perf: bool,
+}
 
 impl Command for Doctor {
     fn run(self, mut config: Config, out: &mut Output) -> Result<()> {
-        let ts = ToolsetBuilder::new().build(&mut config)?;
-        rtxprintln!(out, "{}", rtx_version());
-        rtxprintln!(out, "{}", shell());
-        rtxprintln!(out, "{}", rtx_env_vars());
-        rtxprintln!(
-            out,
-            "{}\n{}\n",
-            style("settings:").bold(),
-            indent(config.settings.to_string())
-        );
-        rtxprintln!(out, "{}", render_config_files(&config));
-        rtxprintln!(out, "{}", render_plugins(&config));
-        rtxprintln!(
-            out,
-            "{}\n{}\n",
-            style("toolset:").bold(),
-            indent(ts.to_string())
-        );
-
-        let mut checks = Vec::new();
-        for plugin in config.plugins.values() {
-            if !plugin.is_installed() {
-                checks.push(format!("plugin {} is not installed", plugin.name));
-                continue;
+        let mut metrics = self.perf.then(Metrics::new);
+
+        let ts = measure(&mut metrics, "toolset_build", || {
+            ToolsetBuilder::new().build(&mut config)
+        })?;
+
+        let shell = shell_info();
+        let env = rtx_env_vars();
+        let config_files = config_files(&config);
+        let mut plugins = plugins(&config, &mut metrics);
+
+        let mut checks = build_checks(&config, &plugins);
+        let mut fix_results = Vec::new();
+
+        if self.fix {
+            let mut applied_restart_required = std::collections::HashSet::new();
+            for check in &checks {
+                if let Some(fix) = &check.fix {
+                    let (outcome, error) = match fix(&mut config) {
+                        Ok(()) if check.restart_required => (FixOutcome::Applied, None),
+                        Ok(()) => (FixOutcome::Fixed, None),
+                        Err(e) => (FixOutcome::Failed, Some(e.to_string())),
+                    };
+                    if matches!(outcome, FixOutcome::Applied) {
+                        applied_restart_required.insert(check.description.clone());
+                    }
+                    fix_results.push(FixResult {
+                        description: check.description.clone(),
+                        outcome,
+                        error,
+                    });
+                }
+            }
+            // Re-derive plugin install state, since a fix may have just installed one.
+            plugins = self::plugins(&config, &mut None);
+            // The in-process `config` can't observe the effect of a restart-required
+            // fix (e.g. activation) even after it succeeded on disk, so drop those
+            // checks here instead of re-reporting them as still-broken and exiting 1.
+            checks = build_checks(&config, &plugins)
+                .into_iter()
+                .filter(|c| !applied_restart_required.contains(&c.description))
+                .collect();
+        }
+
+        let problems: Vec<Problem> = checks
+            .iter()
+            .map(|c| Problem {
+                description: c.description.clone(),
+                snippet: c.snippet.clone(),
+            })
+            .collect();
+
+        if self.json {
+            let report = DoctorReport {
+                version: VERSION.to_string(),
+                shell,
+                env,
+                settings: serde_json::to_value(&config.settings)?,
+                config_files,
+                plugins,
+                toolset: serde_json::to_value(&ts)?,
+                problems: problems.clone(),
+                fixes: fix_results.clone(),
+                metrics: metrics.as_ref().map(Metrics::sorted),
+            };
+            rtxprintln!(out, "{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            rtxprintln!(out, "{}\n  {}\n", style("rtx version:").bold(), VERSION.to_string());
+            let shell_display = match &shell {
+                Some(shell) => indent(shell.to_string()),
+                None => "  (unknown)\n".to_string(),
+            };
+            rtxprintln!(out, "{}\n{}\n", style("shell:").bold(), shell_display);
+            rtxprintln!(out, "{}\n{}", style("rtx environment variables:").bold(), env);
+            rtxprintln!(
+                out,
+                "{}\n{}\n",
+                style("settings:").bold(),
+                indent(config.settings.to_string())
+            );
+            rtxprintln!(out, "{}\n{}", style("config files:").bold(), config_files);
+            rtxprintln!(out, "{}\n{}", style("plugins:").bold(), plugins);
+            rtxprintln!(
+                out,
+                "{}\n{}\n",
+                style("toolset:").bold(),
+                indent(ts.to_string())
+            );
+
+            if !fix_results.is_empty() {
+                rtxprintln!(out, "{}", style("fixes applied:").bold());
+                for fix in &fix_results {
+                    match &fix.outcome {
+                        FixOutcome::Fixed => {
+                            rtxprintln!(out, "  {} {}", style("[fixed]").green(), fix.description)
+                        }
+                        FixOutcome::Applied => rtxprintln!(
+                            out,
+                            "  {} {} (restart your shell for this to take effect)",
+                            style("[applied]").yellow(),
+                            fix.description
+                        ),
+                        FixOutcome::Failed => rtxprintln!(
+                            out,
+                            "  {} {}: {}",
+                            style("[failed]").red(),
+                            fix.description,
+                            fix.error.as_deref().unwrap_or("unknown error")
+                        ),
+                    }
+                }
+                rtxprintln!(out, "");
+            }
+
+            if let Some(metrics) = &metrics {
+                rtxprintln!(out, "{}\n{}", style("perf:").bold(), metrics.sorted());
+            }
+
+            if problems.is_empty() {
+                rtxprintln!(out, "No problems found");
+            } else {
+                let checks_plural = if problems.len() == 1 { "" } else { "s" };
+                let summary = format!("{} problem{checks_plural} found:", problems.len());
+                rtxprintln!(out, "{}", style(summary).red().bold());
+                for problem in &problems {
+                    rtxprintln!(out, "{}\n", problem.description);
+                    if let Some(snippet) = &problem.snippet {
+                        rtxprintln!(out, "  add this to your shell's rc file:\n    {}\n", snippet);
+                    }
+                }
             }
         }
 
-        if let Some(latest) = cli::version::check_for_new_version() {
-            checks.push(format!(
+        if !problems.is_empty() {
+            exit(1);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DoctorReport {
+    version: String,
+    shell: Option<ShellInfo>,
+    env: EnvVars,
+    settings: serde_json::Value,
+    config_files: ConfigFiles,
+    plugins: Plugins,
+    toolset: serde_json::Value,
+    problems: Vec<Problem>,
+    fixes: Vec<FixResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<Metrics>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Problem {
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FixOutcome {
+    /// The problem is fully resolved.
+    Fixed,
+    /// The fix succeeded but only takes effect once the user starts a new shell session.
+    Applied,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FixResult {
+    description: String,
+    outcome: FixOutcome,
+    error: Option<String>,
+}
+
+/// A detected problem with rtx's installation or configuration, with an
+/// optional remediation that `rtx doctor --fix` can apply.
+struct Check {
+    description: String,
+    /// A shell snippet to show the user, e.g. an activation line to add to an rc file.
+    snippet: Option<String>,
+    /// Set when a successful fix can't be verified to have taken effect in this
+    /// process, e.g. because it only changes a new shell's environment.
+    restart_required: bool,
+    fix: Option<Box<dyn Fn(&mut Config) -> Result<()>>>,
+}
+
+fn build_checks(config: &Config, plugins: &Plugins) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    for plugin in &plugins.0 {
+        if !plugin.installed {
+            let name = plugin.name.clone();
+            checks.push(Check {
+                description: format!("plugin {} is not installed", name),
+                snippet: None,
+                restart_required: false,
+                fix: Some(Box::new(move |_config: &mut Config| -> Result<()> {
+                    cmd!(rtx_exe(), "plugins", "install", &name).run()?;
+                    Ok(())
+                })),
+            });
+        }
+    }
+
+    if let Some(latest) = cli::version::check_for_new_version() {
+        checks.push(Check {
+            description: format!(
                 "new rtx version {} available, currently on {}",
                 latest,
                 env!("CARGO_PKG_VERSION")
-            ));
+            ),
+            snippet: None,
+            restart_required: true,
+            fix: Some(Box::new(|_config: &mut Config| -> Result<()> {
+                cmd!(rtx_exe(), "self-update").run()?;
+                Ok(())
+            })),
+        });
+    }
+
+    if !config.is_activated() {
+        let shell_name = ShellType::load().map(|s| s.to_string());
+        let snippet = shell_name
+            .as_deref()
+            .map(activation_snippet)
+            .unwrap_or_else(|| activation_snippet("bash"));
+        let rc_file = shell_name.as_deref().and_then(rc_file_for_shell);
+        let fix_snippet = snippet.clone();
+        checks.push(Check {
+            description: "rtx is not activated, run `rtx activate` for setup instructions"
+                .to_string(),
+            snippet: Some(snippet),
+            restart_required: true,
+            fix: rc_file.map(|rc_file| {
+                Box::new(move |_config: &mut Config| -> Result<()> {
+                    append_to_rc_file(&rc_file, &fix_snippet)
+                }) as Box<dyn Fn(&mut Config) -> Result<()>>
+            }),
+        });
+    }
+
+    checks
+}
+
+/// Path to the currently running rtx binary, so fixes that shell out to rtx
+/// itself (plugin install, self-update) work even before `rtx` is on `PATH`.
+fn rtx_exe() -> PathBuf {
+    std::env::current_exe().unwrap_or_else(|_| PathBuf::from("rtx"))
+}
+
+fn activation_snippet(shell: &str) -> String {
+    match shell {
+        "fish" => "rtx activate fish | source".to_string(),
+        _ => format!("eval \"$(rtx activate {shell})\""),
+    }
+}
+
+fn rc_file_for_shell(shell: &str) -> Option<PathBuf> {
+    let home = PathBuf::from(env::HOME.to_string());
+    match shell {
+        "bash" => Some(home.join(".bashrc")),
+        "zsh" => Some(home.join(".zshrc")),
+        "fish" => Some(home.join(".config/fish/config.fish")),
+        _ => None,
+    }
+}
+
+fn append_to_rc_file(rc_file: &Path, snippet: &str) -> Result<()> {
+    let contents = std::fs::read_to_string(rc_file).unwrap_or_default();
+    if contents.contains(snippet) {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(rc_file)?;
+    writeln!(file, "\n{snippet}")?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct ShellInfo {
+    path: String,
+    version: String,
+}
+
+impl Display for ShellInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n{}", self.path, self.version)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EnvVars(Vec<(String, String)>);
+
+impl Display for EnvVars {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return writeln!(f, "  (none)");
+        }
+        for (k, v) in &self.0 {
+            writeln!(f, "  {k}={v}")?;
         }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ConfigFiles(Vec<PathBuf>);
 
-        if !config.is_activated() {
-            let cmd = style("rtx activate").yellow().for_stderr();
-            checks.push(format!(
-                "rtx is not activated, run `{cmd}` for setup instructions"
-            ));
+impl Display for ConfigFiles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.0 {
+            writeln!(f, "  {}", file.display())?;
         }
+        Ok(())
+    }
+}
 
-        if checks.is_empty() {
-            rtxprintln!(out, "No problems found");
-        } else {
-            let checks_plural = if checks.len() == 1 { "" } else { "s" };
-            let summary = format!("{} problem{checks_plural} found:", checks.len());
-            rtxprintln!(out, "{}", style(summary).red().bold());
-            for check in &checks {
-                rtxprintln!(out, "{}\n", check);
+#[derive(Debug, Serialize)]
+struct PluginInfo {
+    name: String,
+    url: Option<String>,
+    sha: Option<String>,
+    installed: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Plugins(Vec<PluginInfo>);
+
+impl Display for Plugins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let max_plugin_name_len = self.0.iter().map(|p| p.name.len()).max().unwrap_or(0);
+        for p in &self.0 {
+            let padded_name = pad_str(&p.name, max_plugin_name_len, Alignment::Left, None);
+            match (&p.url, &p.sha) {
+                (Some(url), Some(sha)) => writeln!(f, "  {padded_name} {url}#{sha}")?,
+                _ => writeln!(f, "  {padded_name}")?,
             }
-            exit(1);
         }
+        Ok(())
+    }
+}
 
+#[derive(Debug, Clone, Serialize)]
+struct Metric {
+    name: String,
+    duration_ms: f64,
+}
+
+/// Collects named timings for `rtx doctor --perf`, e.g. the time spent
+/// building the toolset or probing a single plugin's git metadata.
+#[derive(Debug, Default, Serialize)]
+struct Metrics(Vec<Metric>);
+
+impl Metrics {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&mut self, name: impl Into<String>, duration_ms: f64) {
+        self.0.push(Metric {
+            name: name.into(),
+            duration_ms,
+        });
+    }
+
+    /// A copy of the collected metrics sorted slowest-first, for display.
+    fn sorted(&self) -> Metrics {
+        let mut metrics = self.0.clone();
+        metrics.sort_by(|a, b| b.duration_ms.partial_cmp(&a.duration_ms).unwrap());
+        Metrics(metrics)
+    }
+}
+
+impl Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for metric in &self.0 {
+            writeln!(f, "  {:>8.2}ms  {}", metric.duration_ms, metric.name)?;
+        }
         Ok(())
     }
 }
 
-fn rtx_env_vars() -> String {
+/// Times `f`, recording the duration under `name` if metrics collection is enabled.
+fn measure<T>(metrics: &mut Option<Metrics>, name: impl Into<String>, f: impl FnOnce() -> T) -> T {
+    if metrics.is_none() {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    metrics
+        .as_mut()
+        .unwrap()
+        .push(name, start.elapsed().as_secs_f64() * 1000.0);
+    result
+}
+
+fn rtx_env_vars() -> EnvVars {
     let vars = env::vars()
         .filter(|(k, _)| k.starts_with("RTX_"))
         .collect::<Vec<(String, String)>>();
-    let mut s = style("rtx environment variables:\n").bold().to_string();
-    if vars.is_empty() {
-        s.push_str("  (none)\n");
-    }
-    for (k, v) in vars {
-        s.push_str(&format!("  {}={}\n", k, v));
-    }
-    s
+    EnvVars(vars)
 }
 
-fn render_config_files(config: &Config) -> String {
-    let mut s = style("config files:\n").bold().to_string();
-    for f in config.config_files.keys().rev() {
-        s.push_str(&format!("  {}\n", f.display()));
-    }
-    s
+fn config_files(config: &Config) -> ConfigFiles {
+    ConfigFiles(config.config_files.keys().rev().cloned().collect())
 }
 
-fn render_plugins(config: &Config) -> String {
-    let mut s = style("plugins:\n").bold().to_string();
-    let max_plugin_name_len = config
+fn plugins(config: &Config, metrics: &mut Option<Metrics>) -> Plugins {
+    let infos = config
         .plugins
         .values()
-        .map(|p| p.name.len())
-        .max()
-        .unwrap_or(0);
-    for p in config.plugins.values() {
-        let padded_name = pad_str(&p.name, max_plugin_name_len, Alignment::Left, None);
-        let git = Git::new(p.plugin_path.clone());
-        let si = match git.get_remote_url() {
-            Some(url) => {
-                let sha = git
-                    .current_sha_short()
-                    .unwrap_or_else(|_| "(unknown)".to_string());
-                format!("  {padded_name} {url}#{sha}\n")
+        .map(|p| {
+            let installed = measure(metrics, format!("plugin:{}:is_installed", p.name), || {
+                p.is_installed()
+            });
+            let (url, sha) = measure(metrics, format!("plugin:{}:git", p.name), || {
+                let git = Git::new(p.plugin_path.clone());
+                let url = git.get_remote_url();
+                let sha = url
+                    .as_ref()
+                    .map(|_| git.current_sha_short().unwrap_or_else(|_| "(unknown)".to_string()));
+                (url, sha)
+            });
+            PluginInfo {
+                name: p.name.clone(),
+                url,
+                sha,
+                installed,
             }
-            None => format!("  {padded_name}\n"),
-        };
-        s.push_str(&si);
-    }
-    s
+        })
+        .collect();
+    Plugins(infos)
 }
 
-fn rtx_version() -> String {
-    let mut s = style("rtx version:\n").bold().to_string();
-    s.push_str(&format!("  {}\n", *VERSION));
-    s
-}
+fn shell_info() -> Option<ShellInfo> {
+    let shell_type = ShellType::load()?;
+    let bin_name = shell_type.bin_name();
+    let shell_path = if env::SHELL.ends_with(bin_name) {
+        env::SHELL.to_string()
+    } else {
+        bin_name.to_string()
+    };
 
-fn shell() -> String {
-    let mut s = style("shell:\n").bold().to_string();
-    match ShellType::load().map(|s| s.to_string()) {
-        Some(shell) => {
-            let shell_cmd = if env::SHELL.ends_with(shell.as_str()) {
-                &*env::SHELL
+    let (program, args) = shell_type.version_command();
+    let version = std::process::Command::new(program)
+        .args(&args)
+        .output()
+        .map_err(|e| e.to_string())
+        .and_then(|out| {
+            let raw = String::from_utf8_lossy(if out.status.success() {
+                &out.stdout
             } else {
-                &shell
-            };
-            let version = cmd!(shell_cmd, "--version")
-                .read()
-                .unwrap_or_else(|e| format!("failed to get shell version: {}", e));
-            let out = format!("{}\n{}\n", shell_cmd, version);
-            s.push_str(&indent(out));
-        }
-        None => s.push_str("  (unknown)\n"),
-    }
-    s
+                &out.stderr
+            });
+            Ok(shell_type.parse_version(&raw))
+        })
+        .unwrap_or_else(|e| format!("failed to get shell version: {}", e));
+
+    Some(ShellInfo {
+        path: shell_path,
+        version,
+    })
 }
 
 fn indent(s: String) -> String {
@@ -166,5 +523,73 @@ static AFTER_LONG_HELP: Lazy<String> = Lazy::new(|| {
     {}
       $ rtx doctor
       [WARN] plugin nodejs is not installed
+      $ rtx doctor --json
+      {{
+        "version": "2.0.0",
+        "shell": {{
+          "path": "/bin/zsh",
+          "version": "zsh 5.9 (x86_64-apple-darwin)"
+        }},
+        ...
+      }}
+      $ rtx doctor --fix
+      [fixed] plugin nodejs is not installed
+      $ rtx doctor --perf
+          42.10ms  toolset_build
+           3.21ms  plugin:nodejs:git
     "#, style("Examples:").bold().underlined()}
 });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn activation_snippet_fish_uses_source_form() {
+        assert_eq!(activation_snippet("fish"), "rtx activate fish | source");
+    }
+
+    #[test]
+    fn activation_snippet_other_shells_use_eval_form() {
+        assert_eq!(activation_snippet("bash"), r#"eval "$(rtx activate bash)""#);
+        assert_eq!(activation_snippet("zsh"), r#"eval "$(rtx activate zsh)""#);
+    }
+
+    #[test]
+    fn rc_file_for_shell_known_shells() {
+        assert!(rc_file_for_shell("bash")
+            .unwrap()
+            .ends_with(".bashrc"));
+        assert!(rc_file_for_shell("zsh").unwrap().ends_with(".zshrc"));
+        assert!(rc_file_for_shell("fish")
+            .unwrap()
+            .ends_with(".config/fish/config.fish"));
+    }
+
+    #[test]
+    fn rc_file_for_shell_unknown_shell_is_none() {
+        assert!(rc_file_for_shell("nu").is_none());
+        assert!(rc_file_for_shell("xonsh").is_none());
+    }
+
+    #[test]
+    fn metrics_sorted_orders_slowest_first() {
+        let mut metrics = Metrics::new();
+        metrics.push("fast", 1.0);
+        metrics.push("slow", 10.0);
+        metrics.push("medium", 5.0);
+        let sorted = metrics.sorted();
+        let names: Vec<&str> = sorted.0.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["slow", "medium", "fast"]);
+    }
+
+    #[test]
+    fn metrics_sorted_does_not_mutate_original() {
+        let mut metrics = Metrics::new();
+        metrics.push("a", 1.0);
+        metrics.push("b", 2.0);
+        metrics.sorted();
+        let names: Vec<&str> = metrics.0.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+}
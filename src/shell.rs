@@ -0,0 +1,180 @@
+use std::fmt::{self, Display};
+
+use crate::env;
+
+/// The shells rtx knows how to activate into and introspect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellType {
+    Bash,
+    Zsh,
+    Fish,
+    Nu,
+    Xonsh,
+    /// `bin` is the actual binary detected (`pwsh` on macOS/Linux, `powershell`
+    /// or `pwsh` on Windows) since the two are not interchangeable.
+    Powershell { bin: &'static str },
+    Cmd,
+}
+
+impl ShellType {
+    /// Detects the shell the current process is running under, e.g. from `$SHELL`.
+    pub fn load() -> Option<Self> {
+        let shell = env::SHELL.rsplit(['/', '\\']).next()?;
+        let shell = shell.trim_end_matches(".exe");
+        match shell {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            "nu" => Some(Self::Nu),
+            "xonsh" => Some(Self::Xonsh),
+            "pwsh" => Some(Self::Powershell { bin: "pwsh" }),
+            "powershell" => Some(Self::Powershell { bin: "powershell" }),
+            "cmd" => Some(Self::Cmd),
+            _ => None,
+        }
+    }
+
+    /// The program and args used to query this shell's own version. Not every
+    /// shell answers `--version` on its own binary: PowerShell exposes its
+    /// version through `$PSVersionTable` and cmd.exe through `ver`.
+    pub fn version_command(&self) -> (&'static str, Vec<&'static str>) {
+        match self {
+            Self::Bash => ("bash", vec!["--version"]),
+            Self::Zsh => ("zsh", vec!["--version"]),
+            Self::Fish => ("fish", vec!["--version"]),
+            Self::Nu => ("nu", vec!["--version"]),
+            Self::Xonsh => ("xonsh", vec!["--version"]),
+            Self::Powershell { bin } => (
+                bin,
+                vec!["-NoProfile", "-Command", "$PSVersionTable.PSVersion"],
+            ),
+            Self::Cmd => ("cmd", vec!["/c", "ver"]),
+        }
+    }
+
+    /// The actual executable name for this shell, as detected. Distinct from
+    /// `Display`, which normalizes both PowerShell binaries to `"powershell"`
+    /// for use in generic messaging like `rtx activate powershell`.
+    pub fn bin_name(&self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::Nu => "nu",
+            Self::Xonsh => "xonsh",
+            Self::Powershell { bin } => bin,
+            Self::Cmd => "cmd",
+        }
+    }
+
+    /// Extracts just the version string out of the raw output of `version_command`,
+    /// since each shell formats its output differently.
+    pub fn parse_version(&self, raw: &str) -> String {
+        let raw = raw.trim();
+        match self {
+            Self::Powershell { .. } => raw
+                .lines()
+                .find(|line| line.chars().next().is_some_and(|c| c.is_ascii_digit()))
+                .map(|line| {
+                    line.split_whitespace()
+                        .take_while(|tok| tok.chars().all(|c| c.is_ascii_digit()))
+                        .collect::<Vec<_>>()
+                        .join(".")
+                })
+                .unwrap_or_else(|| raw.to_string()),
+            _ => raw.lines().next().unwrap_or(raw).to_string(),
+        }
+    }
+}
+
+impl Display for ShellType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::Nu => "nu",
+            Self::Xonsh => "xonsh",
+            Self::Powershell { .. } => "powershell",
+            Self::Cmd => "cmd",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bin_name_distinguishes_powershell_binaries() {
+        assert_eq!(ShellType::Powershell { bin: "pwsh" }.bin_name(), "pwsh");
+        assert_eq!(
+            ShellType::Powershell { bin: "powershell" }.bin_name(),
+            "powershell"
+        );
+        assert_eq!(ShellType::Bash.bin_name(), "bash");
+    }
+
+    #[test]
+    fn display_normalizes_powershell_binaries() {
+        assert_eq!(ShellType::Powershell { bin: "pwsh" }.to_string(), "powershell");
+        assert_eq!(
+            ShellType::Powershell { bin: "powershell" }.to_string(),
+            "powershell"
+        );
+    }
+
+    #[test]
+    fn parse_version_bash() {
+        let raw = "GNU bash, version 5.2.15(1)-release (x86_64-pc-linux-gnu)";
+        assert_eq!(ShellType::Bash.parse_version(raw), raw);
+    }
+
+    #[test]
+    fn parse_version_zsh() {
+        assert_eq!(ShellType::Zsh.parse_version("zsh 5.9 (x86_64-apple-darwin)"), "zsh 5.9 (x86_64-apple-darwin)");
+    }
+
+    #[test]
+    fn parse_version_fish() {
+        assert_eq!(ShellType::Fish.parse_version("fish, version 3.7.0\n"), "fish, version 3.7.0");
+    }
+
+    #[test]
+    fn parse_version_nu() {
+        assert_eq!(ShellType::Nu.parse_version("0.93.0\n"), "0.93.0");
+    }
+
+    #[test]
+    fn parse_version_xonsh() {
+        assert_eq!(ShellType::Xonsh.parse_version("xonsh/0.18.3\n"), "xonsh/0.18.3");
+    }
+
+    #[test]
+    fn parse_version_cmd() {
+        let raw = "Microsoft Windows [Version 10.0.19045.4170]";
+        assert_eq!(ShellType::Cmd.parse_version(raw), raw);
+    }
+
+    #[test]
+    fn parse_version_powershell_5_table() {
+        let raw = "\nMajor  Minor  Build  Revision\n-----  -----  -----  --------\n5      1      19041  2965\n\n";
+        let shell = ShellType::Powershell { bin: "powershell" };
+        assert_eq!(shell.parse_version(raw), "5.1.19041.2965");
+    }
+
+    #[test]
+    fn parse_version_powershell_7_table() {
+        let raw = "\nMajor  Minor  Patch  PreReleaseLabel BuildLabel\n-----  -----  -----  --------------- ----------\n7      4      2\n\n";
+        let shell = ShellType::Powershell { bin: "pwsh" };
+        assert_eq!(shell.parse_version(raw), "7.4.2");
+    }
+
+    #[test]
+    fn parse_version_powershell_falls_back_to_raw_on_no_digit_line() {
+        let raw = "some unexpected error output";
+        let shell = ShellType::Powershell { bin: "pwsh" };
+        assert_eq!(shell.parse_version(raw), raw);
+    }
+}